@@ -0,0 +1,103 @@
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+use crate::VirtArena;
+
+type DestructorList = Vec<(NonNull<u8>, unsafe fn(*mut u8))>;
+
+/// A [`VirtArena`] layer which additionally tracks the destructors of
+/// non-`Copy` values allocated through [`alloc_value_drop`](DropArena::alloc_value_drop)
+/// and runs them, in reverse allocation order, on [`reset`](DropArena::reset) and on
+/// `Drop` — modeled on rustc's `TypedArena`/`DropArena`.
+///
+/// The plain `alloc_*` methods of the underlying [`VirtArena`] (reachable through
+/// [`Deref`](std::ops::Deref)) remain destructor-free, so callers only pay for
+/// drop tracking when they opt in through `alloc_value_drop`.
+#[derive(Default)]
+pub struct DropArena {
+    arena: VirtArena,
+    destructors: RefCell<DestructorList>,
+    // The recorded function pointers are monomorphized for the `T` that was
+    // allocated, so running them from another thread than the one that
+    // allocated the value is not guaranteed to be sound in general; keep
+    // this type `!Send` to stay on the safe side.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl DropArena {
+    /// Allocates a struct `T` inside the arena, moves `val` into the allocation
+    /// and, if `T` needs dropping, records its destructor to be run on
+    /// [`reset`](Self::reset) or when this arena is dropped.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_value_drop<T>(&self, val: T) -> &mut T {
+        let place = self.arena.alloc_value(val);
+
+        if std::mem::needs_drop::<T>() {
+            let ptr = NonNull::from(&*place).cast();
+            let drop_fn: unsafe fn(*mut u8) = |p| unsafe { std::ptr::drop_in_place(p as *mut T) };
+            self.destructors.borrow_mut().push((ptr, drop_fn));
+        }
+
+        place
+    }
+
+    /// Runs all recorded destructors, in reverse allocation order, then
+    /// resets the underlying arena storage, invalidating all the references
+    /// allocated.
+    pub fn reset(&mut self) {
+        self.run_destructors();
+        self.arena.reset();
+    }
+
+    fn run_destructors(&mut self) {
+        for (ptr, drop_fn) in self.destructors.get_mut().drain(..).rev() {
+            unsafe { drop_fn(ptr.as_ptr()) };
+        }
+    }
+}
+
+impl std::ops::Deref for DropArena {
+    type Target = VirtArena;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl Drop for DropArena {
+    fn drop(&mut self) {
+        self.run_destructors();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn runs_destructors_on_reset_and_drop() {
+        let counter = Rc::new(RefCell::new(0));
+
+        let mut arena = DropArena::default();
+
+        struct Bump(Rc<RefCell<i32>>);
+        impl Drop for Bump {
+            fn drop(&mut self) {
+                *self.0.borrow_mut() += 1;
+            }
+        }
+
+        arena.alloc_value_drop(Bump(counter.clone()));
+        arena.alloc_value_drop(Bump(counter.clone()));
+        assert_eq!(*counter.borrow(), 0);
+
+        arena.reset();
+        assert_eq!(*counter.borrow(), 2);
+
+        arena.alloc_value_drop(Bump(counter.clone()));
+        drop(arena);
+        assert_eq!(*counter.borrow(), 3);
+    }
+}
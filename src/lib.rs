@@ -1,8 +1,13 @@
+mod drop_arena;
+mod sync;
 #[cfg(unix)]
 mod unix;
 #[cfg(windows)]
 mod windows;
 
+pub use drop_arena::DropArena;
+pub use sync::SyncVirtArena;
+
 use std::{alloc::Layout, mem::MaybeUninit, ptr::NonNull};
 
 #[cfg(unix)]
@@ -16,11 +21,45 @@ type RawArena = windows::VirtArena;
 pub struct VirtArena(RawArena);
 
 impl VirtArena {
+    /// Returns a builder to construct a [`VirtArena`] with a custom
+    /// reservation size instead of the default 128 GiB.
+    pub fn builder() -> VirtArenaBuilder {
+        VirtArenaBuilder::default()
+    }
+
     /// Allocates a memory for the given `layout`.
     pub fn alloc(&self, layout: Layout) -> NonNull<u8> {
         self.0.alloc(layout)
     }
 
+    /// Allocates memory for the given `layout`, returning an error instead
+    /// of panicking if the arena's reservation would be exhausted (or, on
+    /// Windows, if committing the backing pages fails).
+    pub fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.0.try_alloc(layout)
+    }
+
+    /// Allocates memory for struct `T`, returning an error instead of
+    /// panicking if the arena's reservation would be exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn try_alloc_uninit<T: Sized>(&self) -> Result<&mut MaybeUninit<T>, AllocError> {
+        let layout = Layout::new::<T>();
+        let ptr = self.0.try_alloc(layout)?;
+        Ok(unsafe { ptr.cast().as_mut() })
+    }
+
+    /// Allocates memory for slice `[T]`, returning an error instead of
+    /// panicking if the arena's reservation would be exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn try_alloc_slice_uninit<T: Sized>(
+        &self,
+        count: usize,
+    ) -> Result<&mut [MaybeUninit<T>], AllocError> {
+        let layout = Layout::array::<T>(count).expect("Failed to read the array layout");
+        let ptr = self.0.try_alloc(layout)?.cast();
+        Ok(unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), count) })
+    }
+
     /// Allocates a struct `T` inside the arena and clears its memory to 0.
     ///
     /// # Safety
@@ -66,12 +105,49 @@ impl VirtArena {
         unsafe { uninit.assume_init_mut() }
     }
 
-    /// Allocates a struct `T` inside the arena and moves `val` into the allocation.    
+    /// Allocates a struct `T` inside the arena and moves `val` into the allocation.
     #[allow(clippy::mut_from_ref)]
     pub fn alloc_value<T: Sized>(&self, val: T) -> &mut T {
         self.alloc_with(move || val)
     }
 
+    /// Bump-allocates a copy of the slice `src` inside the arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &mut [T] {
+        let uninit = self.alloc_slice_uninit::<T>(src.len());
+        let ptr = uninit.as_mut_ptr().cast::<T>();
+        unsafe {
+            std::ptr::copy_nonoverlapping(src.as_ptr(), ptr, src.len());
+            std::slice::from_raw_parts_mut(ptr, src.len())
+        }
+    }
+
+    /// Bump-allocates a copy of the string `s` inside the arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(&self, s: &str) -> &mut str {
+        let bytes = self.alloc_slice_copy(s.as_bytes());
+        unsafe { std::str::from_utf8_unchecked_mut(bytes) }
+    }
+
+    /// Bump-allocates a slice inside the arena containing the items produced
+    /// by `iter`. Since a single arena allocation cannot grow once the cursor
+    /// has moved on, the items are first collected to learn their count, then
+    /// moved into the arena slice without running their destructors.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_from_iter<T, I: IntoIterator<Item = T>>(&self, iter: I) -> &mut [T] {
+        let mut values: Vec<T> = iter.into_iter().collect();
+        let uninit = self.alloc_slice_uninit::<T>(values.len());
+        let ptr = uninit.as_mut_ptr().cast::<T>();
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(values.as_ptr(), ptr, values.len());
+            // The elements now live in the arena; forget about them here so
+            // the `Vec`'s drop doesn't double-free/double-drop them.
+            values.set_len(0);
+            std::slice::from_raw_parts_mut(ptr, uninit.len())
+        }
+    }
+
     /// Returns the number of bytes currently allocated from the arena.
     pub fn bytes_used(&self) -> usize {
         self.0.bytes_used()
@@ -82,18 +158,166 @@ impl VirtArena {
     pub fn reset(&mut self) {
         self.0.reset()
     }
+
+    /// Like [`VirtArena::reset`], but additionally returns the physical
+    /// pages touched since the arena was created (or last decommitted) back
+    /// to the OS, lowering RSS for long-running processes that cycle a
+    /// large arena per frame/request. Only pages up to the high-water mark
+    /// are decommitted, and they fault back to zero lazily on next use.
+    /// Prefer the cheap [`VirtArena::reset`] when keeping the committed
+    /// working set around is preferable to lowering memory usage.
+    pub fn reset_and_decommit(&mut self) {
+        self.0.reset_and_decommit()
+    }
+
+    /// Snapshots the current cursor position so it can later be restored
+    /// with [`VirtArena::restore`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            bytes_used: self.bytes_used(),
+        }
+    }
+
+    /// Rewinds the arena cursor back to an earlier `cp`, invalidating all
+    /// the references allocated since it was taken. Like [`VirtArena::reset`]
+    /// this does not run destructors.
+    pub fn restore(&mut self, cp: Checkpoint) {
+        self.restore_from_checkpoint(cp);
+    }
+
+    /// Opens a scoped sub-arena: allocations made through the returned
+    /// [`Scope`] borrow it, and the `Scope` itself holds an exclusive borrow
+    /// of this arena, so no other `Scope` can be opened (or dropped, rewinding
+    /// the cursor) while this one is alive. When the `Scope` is dropped the
+    /// cursor rewinds back to where it was created, reclaiming the bytes for
+    /// reuse without a full [`VirtArena::reset`].
+    pub fn scope(&mut self) -> Scope<'_> {
+        let checkpoint = self.checkpoint();
+        Scope {
+            arena: self,
+            checkpoint,
+        }
+    }
+
+    fn restore_from_checkpoint(&self, cp: Checkpoint) {
+        self.0.restore(cp.bytes_used);
+    }
+}
+
+/// A snapshot of a [`VirtArena`]'s cursor position, taken with
+/// [`VirtArena::checkpoint`] and later passed to [`VirtArena::restore`].
+#[derive(Clone, Copy, Debug)]
+pub struct Checkpoint {
+    bytes_used: usize,
+}
+
+/// A guard returned by [`VirtArena::scope`] (or [`Scope::scope`] for
+/// nesting). It holds an exclusive borrow of the parent arena, so the
+/// borrow checker enforces strict stack discipline: a parent `Scope` (or
+/// the root `VirtArena`) cannot be used, re-scoped or dropped while a child
+/// `Scope` borrowed from it is still alive. Allocations made through the
+/// guard borrow it in turn and cannot outlive it; dropping the guard
+/// rewinds the arena cursor back to where the scope was opened.
+pub struct Scope<'a> {
+    arena: &'a mut VirtArena,
+    checkpoint: Checkpoint,
+}
+
+impl<'a> Scope<'a> {
+    /// Allocates a struct `T` inside the scope and moves `val` into the allocation.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_value<T: Sized>(&self, val: T) -> &mut T {
+        self.arena.alloc_value(val)
+    }
+
+    /// Allocates a struct `T` inside the scope and sets its content to the output of `fun`.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_with<T: Sized>(&self, fun: impl FnOnce() -> T) -> &mut T {
+        self.arena.alloc_with(fun)
+    }
+
+    /// Opens a nested sub-scope. Mirrors [`VirtArena::scope`]: the returned
+    /// `Scope` exclusively borrows this one, so this scope cannot be used
+    /// or dropped until the nested one is.
+    pub fn scope(&mut self) -> Scope<'_> {
+        let checkpoint = self.arena.checkpoint();
+        Scope {
+            arena: &mut *self.arena,
+            checkpoint,
+        }
+    }
+
+    /// Returns the number of bytes currently allocated from the arena.
+    pub fn bytes_used(&self) -> usize {
+        self.arena.bytes_used()
+    }
+}
+
+impl Drop for Scope<'_> {
+    fn drop(&mut self) {
+        self.arena.restore_from_checkpoint(self.checkpoint);
+    }
 }
 
 // We don't use any thread local storage so this should be fine
 unsafe impl Send for VirtArena {}
 
+/// Builder for a [`VirtArena`] with a custom virtual address space
+/// reservation, for targets or processes where the default 128 GiB
+/// reservation is not appropriate (e.g. 32-bit targets, or processes
+/// running under a tight `vm.overcommit`/address-space limit).
+pub struct VirtArenaBuilder {
+    reserve_size: usize,
+}
+
+impl Default for VirtArenaBuilder {
+    fn default() -> Self {
+        Self {
+            reserve_size: VIRT_ALLOC_SIZE,
+        }
+    }
+}
+
+impl VirtArenaBuilder {
+    /// Sets the number of bytes of virtual address space to reserve.
+    pub fn reserve_size(mut self, bytes: usize) -> Self {
+        self.reserve_size = bytes;
+        self
+    }
+
+    /// Reserves the configured address space and builds the [`VirtArena`].
+    pub fn build(self) -> VirtArena {
+        VirtArena(RawArena::new(self.reserve_size))
+    }
+}
+
+/// Error returned when an allocation would exceed a [`VirtArena`]'s reserved
+/// address space, or (on Windows) when committing the backing pages fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl std::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("virt arena allocation failed: reservation exhausted or commit failed")
+    }
+}
+
+impl std::error::Error for AllocError {}
+
 const VIRT_ALLOC_SIZE: usize = 128 * (1 << 30); // 128 GiB is assumed to be enoght for every use case of this arena
 
 trait VirtArenaRaw {
     fn bytes_used(&self) -> usize;
     fn reset(&mut self);
+    fn restore(&self, bytes_used: usize);
+    fn reset_and_decommit(&mut self);
+
+    fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
 
-    fn alloc(&self, layout: Layout) -> NonNull<u8>;
+    fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        self.try_alloc(layout)
+            .expect("Failed to allocate virtual arena memory")
+    }
 
     unsafe fn alloc_zeroed(&self, layout: Layout) -> NonNull<u8> {
         let ptr = self.alloc(layout);
@@ -132,4 +356,83 @@ mod tests {
         let test2 = unsafe { arena.alloc_uninit::<Test>().assume_init_mut() };
         assert!(test2.thing.is_none());
     }
+
+    #[test]
+    fn scope_rewinds_cursor() {
+        let mut arena = VirtArena::default();
+
+        arena.alloc_value(1u64);
+        let bytes_before = arena.bytes_used();
+
+        {
+            let scope = arena.scope();
+            scope.alloc_value([0u8; 4096]);
+            scope.alloc_value(2u64);
+            assert!(scope.bytes_used() > bytes_before);
+        }
+
+        assert_eq!(arena.bytes_used(), bytes_before);
+    }
+
+    #[test]
+    fn nested_scope_rewinds_to_parent_checkpoint() {
+        let mut arena = VirtArena::default();
+
+        arena.alloc_value(1u64);
+        let bytes_before = arena.bytes_used();
+
+        {
+            let mut outer = arena.scope();
+            outer.alloc_value([0u8; 4096]);
+            let bytes_in_outer = outer.bytes_used();
+
+            {
+                let inner = outer.scope();
+                inner.alloc_value([0u8; 4096]);
+                assert!(inner.bytes_used() > bytes_in_outer);
+            }
+
+            // Dropping the inner scope rewinds only back to the outer's
+            // checkpoint, not all the way to `bytes_before`.
+            assert_eq!(outer.bytes_used(), bytes_in_outer);
+        }
+
+        assert_eq!(arena.bytes_used(), bytes_before);
+    }
+
+    #[test]
+    fn try_alloc_reports_exhausted_reservation() {
+        let arena = VirtArena::builder().reserve_size(4096).build();
+
+        assert!(arena.try_alloc_uninit::<[u8; 4096]>().is_ok());
+        assert_eq!(arena.try_alloc_uninit::<u8>().unwrap_err(), AllocError);
+    }
+
+    #[test]
+    fn slice_and_iter_helpers() {
+        let arena = VirtArena::default();
+
+        let copied = arena.alloc_slice_copy(&[1, 2, 3]);
+        assert_eq!(copied, &[1, 2, 3]);
+
+        let interned = arena.alloc_str("hello");
+        assert_eq!(interned, "hello");
+
+        let collected = arena.alloc_from_iter((0..5).map(|n| n * 2));
+        assert_eq!(collected, &[0, 2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn reset_and_decommit_rewinds_and_allows_reuse() {
+        let mut arena = VirtArena::default();
+
+        arena.alloc_value([0u8; 4096]);
+        assert!(arena.bytes_used() > 0);
+
+        arena.reset_and_decommit();
+        assert_eq!(arena.bytes_used(), 0);
+
+        let value = arena.alloc_value(7u32);
+        assert_eq!(*value, 7);
+    }
 }
@@ -0,0 +1,139 @@
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+use std::{alloc::Layout, mem::MaybeUninit, ptr::NonNull};
+
+#[cfg(unix)]
+type RawSyncArena = unix::VirtArena;
+#[cfg(windows)]
+type RawSyncArena = windows::VirtArena;
+
+/// A thread-safe variant of [`crate::VirtArena`].
+///
+/// [`crate::VirtArena`] advances its cursor through a plain `Cell`, so
+/// concurrent allocation through a shared `&VirtArena` is undefined
+/// behavior. `SyncVirtArena` instead advances an atomic bump pointer with a
+/// compare-exchange loop, making `alloc` (and therefore the whole type) safe
+/// to call from multiple threads through a shared reference.
+#[derive(Default)]
+pub struct SyncVirtArena(RawSyncArena);
+
+impl SyncVirtArena {
+    /// Allocates a memory for the given `layout`.
+    pub fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        self.0.alloc(layout)
+    }
+
+    /// Allocates a struct `T` inside the arena and clears its memory to 0.
+    ///
+    /// # Safety
+    /// Look into [std::mem::zeroed] for safety concerns.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn alloc_zeroed<T: Sized>(&self) -> &mut T {
+        let layout = Layout::new::<T>();
+        self.0.alloc_zeroed(layout).cast().as_mut()
+    }
+
+    /// Allocates memory for struct `T`.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_uninit<T: Sized>(&self) -> &mut MaybeUninit<T> {
+        let layout = Layout::new::<T>();
+        unsafe { self.0.alloc(layout).cast().as_mut() }
+    }
+
+    /// Allocates a struct `T` inside the arena and sets its
+    /// content to the output of `fun`.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_with<T: Sized>(&self, fun: impl FnOnce() -> T) -> &mut T {
+        let uninit = self.alloc_uninit();
+        uninit.write(fun());
+        unsafe { uninit.assume_init_mut() }
+    }
+
+    /// Allocates a struct `T` inside the arena and moves `val` into the allocation.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_value<T: Sized>(&self, val: T) -> &mut T {
+        self.alloc_with(move || val)
+    }
+
+    /// Returns the number of bytes currently allocated from the arena.
+    pub fn bytes_used(&self) -> usize {
+        self.0.bytes_used()
+    }
+
+    /// Restes the arena storage, Invalidating all the references allocated.
+    /// This method does not run the destructors! Those need to be run manually.
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+}
+
+unsafe impl Send for SyncVirtArena {}
+// SAFETY: `alloc` advances the cursor with an atomic compare-exchange loop
+// instead of the non-atomic `Cell` used by `VirtArena`, so concurrent
+// allocation through a shared reference is sound.
+unsafe impl Sync for SyncVirtArena {}
+
+trait SyncVirtArenaRaw {
+    fn bytes_used(&self) -> usize;
+    fn reset(&mut self);
+
+    fn alloc(&self, layout: Layout) -> NonNull<u8>;
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> NonNull<u8> {
+        let ptr = self.alloc(layout);
+        ptr.write_bytes(0, layout.size());
+        ptr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn concurrent_alloc_is_pairwise_non_overlapping() {
+        const THREADS: usize = 8;
+        const ALLOCS_PER_THREAD: usize = 1000;
+
+        let arena = Arc::new(SyncVirtArena::default());
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let arena = Arc::clone(&arena);
+                thread::spawn(move || {
+                    (0..ALLOCS_PER_THREAD)
+                        .map(|i| arena.alloc_value(i as u64) as *mut u64 as usize)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut starts: Vec<usize> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+
+        assert_eq!(starts.len(), THREADS * ALLOCS_PER_THREAD);
+
+        starts.sort_unstable();
+        for window in starts.windows(2) {
+            assert!(
+                window[0] + size_of::<u64>() <= window[1],
+                "overlapping allocations at {:#x} and {:#x}",
+                window[0],
+                window[1]
+            );
+        }
+
+        assert_eq!(
+            arena.bytes_used(),
+            THREADS * ALLOCS_PER_THREAD * size_of::<u64>()
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::alloc::Layout;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use libc::*;
+
+pub struct VirtArena {
+    start: NonNull<u8>,
+    cursor: AtomicUsize,
+}
+
+unsafe impl Send for VirtArena {}
+unsafe impl Sync for VirtArena {}
+
+impl Default for VirtArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for VirtArena {
+    fn drop(&mut self) {
+        unsafe { munmap(self.start.as_ptr().cast(), crate::VIRT_ALLOC_SIZE) };
+    }
+}
+
+impl VirtArena {
+    fn new() -> Self {
+        let start = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                crate::VIRT_ALLOC_SIZE,
+                PROT_READ | PROT_WRITE,
+                MAP_ANONYMOUS | MAP_PRIVATE | MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+
+        if start == MAP_FAILED {
+            panic!(
+                "Failed to allocate virtual arena: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        let start = NonNull::new(start.cast()).expect("mmaped pointer should never be NULL");
+
+        Self {
+            start,
+            cursor: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl crate::sync::SyncVirtArenaRaw for VirtArena {
+    fn bytes_used(&self) -> usize {
+        self.cursor.load(Ordering::Acquire)
+    }
+
+    fn reset(&mut self) {
+        *self.cursor.get_mut() = 0;
+    }
+
+    fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        let mut current = self.cursor.load(Ordering::Relaxed);
+
+        loop {
+            let base = unsafe { self.start.as_ptr().add(current) };
+            let off = base.align_offset(layout.align());
+            let start_off = current + off;
+            let end_off = start_off + layout.size();
+
+            if end_off > crate::VIRT_ALLOC_SIZE {
+                panic!("OOM");
+            }
+
+            match self.cursor.compare_exchange_weak(
+                current,
+                end_off,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    let ptr = unsafe { self.start.as_ptr().add(start_off) };
+                    return NonNull::new(ptr).expect("bump pointer should never be NULL");
+                }
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::alloc::Layout;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use windows::Win32::System::Memory::{
+    VirtualAlloc, VirtualFree, MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_READWRITE,
+};
+
+pub struct VirtArena {
+    start: NonNull<u8>,
+    alloc_cursor: AtomicUsize,
+    // Guards growing the committed region so two threads racing past the
+    // same uncommitted block never both call `VirtualAlloc` on it.
+    commit_cursor: Mutex<usize>,
+}
+
+const COMMIT_BLOCK_SIZE: usize = 1 << 20; // 1MiB
+
+unsafe impl Send for VirtArena {}
+unsafe impl Sync for VirtArena {}
+
+impl Default for VirtArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for VirtArena {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = VirtualFree(self.start.as_ptr() as *mut _, 0, MEM_RELEASE);
+        }
+    }
+}
+
+impl VirtArena {
+    fn new() -> Self {
+        let start =
+            unsafe { VirtualAlloc(None, crate::VIRT_ALLOC_SIZE, MEM_RESERVE, PAGE_READWRITE) };
+
+        let Some(start) = NonNull::new(start.cast()) else {
+            panic!(
+                "Failed to allocate virtual arena: {}",
+                std::io::Error::last_os_error()
+            );
+        };
+
+        Self {
+            start,
+            alloc_cursor: AtomicUsize::new(0),
+            commit_cursor: Mutex::new(0),
+        }
+    }
+
+    fn ensure_committed(&self, end_off: usize) {
+        let mut committed = self.commit_cursor.lock().unwrap();
+        while *committed < end_off {
+            let ptr = unsafe {
+                VirtualAlloc(
+                    Some(self.start.as_ptr().add(*committed) as *const _),
+                    COMMIT_BLOCK_SIZE,
+                    MEM_COMMIT,
+                    PAGE_READWRITE,
+                )
+            };
+            if ptr.is_null() {
+                panic!(
+                    "Failed to commit memory block: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            *committed += COMMIT_BLOCK_SIZE;
+        }
+    }
+}
+
+impl crate::sync::SyncVirtArenaRaw for VirtArena {
+    fn bytes_used(&self) -> usize {
+        self.alloc_cursor.load(Ordering::Acquire)
+    }
+
+    fn reset(&mut self) {
+        *self.alloc_cursor.get_mut() = 0;
+    }
+
+    fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        let mut current = self.alloc_cursor.load(Ordering::Relaxed);
+
+        loop {
+            let base = unsafe { self.start.as_ptr().add(current) };
+            let off = base.align_offset(layout.align());
+            let start_off = current + off;
+            let end_off = start_off + layout.size();
+
+            if end_off > crate::VIRT_ALLOC_SIZE {
+                panic!("OOM");
+            }
+
+            match self.alloc_cursor.compare_exchange_weak(
+                current,
+                end_off,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    self.ensure_committed(end_off);
+                    let ptr = unsafe { self.start.as_ptr().add(start_off) };
+                    return NonNull::new(ptr).expect("bump pointer should never be NULL");
+                }
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
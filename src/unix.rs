@@ -1,33 +1,38 @@
 use std::alloc::Layout;
 use std::cell::Cell;
-use std::mem::MaybeUninit;
 use std::ptr::NonNull;
 
 use libc::*;
 
+use crate::AllocError;
+
 pub struct VirtArena {
     start: NonNull<u8>,
     cursor: Cell<NonNull<u8>>,
+    reserve_size: usize,
+    // Highest `bytes_used` ever reached, so `reset_and_decommit` only
+    // decommits pages that were actually touched.
+    high_water: Cell<usize>,
 }
 
 impl Default for VirtArena {
     fn default() -> Self {
-        Self::new()
+        Self::new(crate::VIRT_ALLOC_SIZE)
     }
 }
 
 impl Drop for VirtArena {
     fn drop(&mut self) {
-        unsafe { munmap(self.start.as_ptr().cast(), super::VIRT_ALLOC_SIZE) };
+        unsafe { munmap(self.start.as_ptr().cast(), self.reserve_size) };
     }
 }
 
 impl VirtArena {
-    fn new() -> Self {
+    pub(crate) fn new(reserve_size: usize) -> Self {
         let start = unsafe {
             mmap(
                 std::ptr::null_mut(),
-                super::VIRT_ALLOC_SIZE,
+                reserve_size,
                 PROT_READ | PROT_WRITE,
                 MAP_ANONYMOUS | MAP_PRIVATE | MAP_NORESERVE,
                 -1,
@@ -46,6 +51,8 @@ impl VirtArena {
         Self {
             start,
             cursor: Cell::new(start),
+            reserve_size,
+            high_water: Cell::new(0),
         }
     }
 }
@@ -59,23 +66,38 @@ impl crate::VirtArenaRaw for VirtArena {
         self.cursor.set(self.start);
     }
 
-    fn alloc_uninit<T: Sized>(&self) -> &mut MaybeUninit<T> {
-        let layout = Layout::new::<MaybeUninit<T>>();
+    fn restore(&self, bytes_used: usize) {
+        self.cursor.set(unsafe { self.start.byte_add(bytes_used) });
+    }
 
-        let ptr: NonNull<MaybeUninit<T>> = self.cursor.get().cast();
+    fn reset_and_decommit(&mut self) {
+        self.cursor.set(self.start);
 
+        let bytes = self.high_water.get();
+        if bytes > 0 {
+            unsafe { madvise(self.start.as_ptr().cast(), bytes, MADV_DONTNEED) };
+            self.high_water.set(0);
+        }
+    }
+
+    fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.cursor.get();
         let off = ptr.align_offset(layout.align());
 
         unsafe {
-            let mut ptr = ptr.byte_add(off);
-            let cursor: NonNull<u8> = ptr.byte_add(layout.size()).cast();
+            let start = ptr.byte_add(off);
+            let cursor = start.byte_add(layout.size());
+            let bytes_used = cursor.byte_offset_from(self.start) as usize;
 
-            if cursor.byte_offset_from(self.start) as usize > super::VIRT_ALLOC_SIZE {
-                panic!("OOM");
+            if bytes_used > self.reserve_size {
+                return Err(AllocError);
             }
             self.cursor.set(cursor);
+            if bytes_used > self.high_water.get() {
+                self.high_water.set(bytes_used);
+            }
 
-            ptr.as_mut()
+            Ok(start)
         }
     }
 }
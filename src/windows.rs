@@ -1,20 +1,23 @@
-use std::{alloc::Layout, cell::Cell, mem::MaybeUninit, ptr::NonNull};
+use std::{alloc::Layout, cell::Cell, ptr::NonNull};
 
 use windows::Win32::System::Memory::{
-    VirtualAlloc, VirtualFree, MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_READWRITE,
+    VirtualAlloc, VirtualFree, MEM_COMMIT, MEM_DECOMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_READWRITE,
 };
 
+use crate::AllocError;
+
 pub struct VirtArena {
     start: NonNull<u8>,
     alloc_cursor: Cell<NonNull<u8>>,
     commit_cursor: Cell<NonNull<u8>>,
+    reserve_size: usize,
 }
 
 const COMMIT_BLOCK_SIZE: usize = 1 << 10; // 1MiB
 
 impl Default for VirtArena {
     fn default() -> Self {
-        Self::new()
+        Self::new(crate::VIRT_ALLOC_SIZE)
     }
 }
 
@@ -27,9 +30,8 @@ impl Drop for VirtArena {
 }
 
 impl VirtArena {
-    fn new() -> Self {
-        let start =
-            unsafe { VirtualAlloc(None, super::VIRT_ALLOC_SIZE, MEM_RESERVE, PAGE_READWRITE) };
+    pub(crate) fn new(reserve_size: usize) -> Self {
+        let start = unsafe { VirtualAlloc(None, reserve_size, MEM_RESERVE, PAGE_READWRITE) };
 
         let Some(start) = NonNull::new(start.cast()) else {
             panic!(
@@ -42,6 +44,7 @@ impl VirtArena {
             start,
             alloc_cursor: Cell::new(start),
             commit_cursor: Cell::new(start),
+            reserve_size,
         }
     }
 }
@@ -55,42 +58,55 @@ impl super::VirtArenaRaw for VirtArena {
         self.alloc_cursor.set(self.start);
     }
 
-    fn alloc_uninit<T: Sized>(&self) -> &mut MaybeUninit<T> {
-        let layout = Layout::new::<MaybeUninit<T>>();
+    fn restore(&self, bytes_used: usize) {
+        // Committed pages are left committed so re-entering the scope stays
+        // cheap; only the allocation cursor rewinds.
+        self.alloc_cursor.set(unsafe { self.start.byte_add(bytes_used) });
+    }
 
-        let ptr: NonNull<MaybeUninit<T>> = self.alloc_cursor.get().cast();
+    fn reset_and_decommit(&mut self) {
+        self.alloc_cursor.set(self.start);
 
+        let committed_bytes =
+            unsafe { self.commit_cursor.get().byte_offset_from(self.start) as usize };
+        if committed_bytes > 0 {
+            unsafe {
+                let _ = VirtualFree(self.start.as_ptr() as *mut _, committed_bytes, MEM_DECOMMIT);
+            }
+            self.commit_cursor.set(self.start);
+        }
+    }
+
+    fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.alloc_cursor.get();
         let off = ptr.align_offset(layout.align());
 
         unsafe {
-            let mut value = ptr.byte_add(off);
-            let cursor: NonNull<u8> = value.byte_add(layout.size()).cast();
+            let value = ptr.byte_add(off);
+            let cursor = value.byte_add(layout.size());
 
-            if cursor.byte_offset_from(self.start) as usize > super::VIRT_ALLOC_SIZE {
-                panic!("OOM");
+            if cursor.byte_offset_from(self.start) as usize > self.reserve_size {
+                return Err(AllocError);
             }
 
-            self.alloc_cursor.set(cursor);
-
-            while self.commit_cursor.get() < self.alloc_cursor.get() {
-                let ptr = VirtualAlloc(
+            while self.commit_cursor.get() < cursor {
+                let committed = VirtualAlloc(
                     Some(self.commit_cursor.get().as_ptr() as *const _),
                     COMMIT_BLOCK_SIZE,
                     MEM_COMMIT,
                     PAGE_READWRITE,
                 );
-                if ptr.is_null() {
-                    panic!(
-                        "Failed to commit memory block: {}",
-                        std::io::Error::last_os_error()
-                    );
+                if committed.is_null() {
+                    return Err(AllocError);
                 }
 
                 self.commit_cursor
-                    .set(self.commit_cursor.get().byte_add(COMMIT_BLOCK_SIZE))
+                    .set(self.commit_cursor.get().byte_add(COMMIT_BLOCK_SIZE));
             }
 
-            value.as_mut()
+            self.alloc_cursor.set(cursor);
+
+            Ok(value)
         }
     }
 }